@@ -0,0 +1,209 @@
+//! User-level autostart via the `HKEY_CURRENT_USER\...\Run` registry key.
+//!
+//! Installing a real service requires administrator rights; this offers the
+//! same "start at logon" behavior for callers who lack them (or whose
+//! machine policy blocks the SCM) by registering the binary under the
+//! current user's `Run` key instead. Since a Run-key process isn't managed
+//! by the SCM, [`is_already_running`] lets callers emulate start/stop by
+//! checking for a prior instance before doing real work.
+
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+
+use windows::core::{Error, Result, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{CloseHandle, ERROR_ALREADY_EXISTS, ERROR_NO_MORE_ITEMS, HANDLE};
+use windows::Win32::System::LibraryLoader::GetModuleFileNameW;
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegCreateKeyExW, RegDeleteValueW, RegEnumValueW, RegOpenKeyExW, RegSetValueExW,
+    HKEY, HKEY_CURRENT_USER, KEY_READ, KEY_WRITE, REG_OPTION_NON_VOLATILE, REG_SZ,
+};
+use windows::Win32::System::Threading::CreateMutexW;
+
+const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+fn wide(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain(std::iter::once(0)).collect()
+}
+
+fn open_run_key_for_write() -> Result<HKEY> {
+    let mut hkey = HKEY::default();
+    unsafe {
+        RegCreateKeyExW(
+            HKEY_CURRENT_USER,
+            PCWSTR(wide(RUN_KEY).as_ptr()),
+            0,
+            PCWSTR::null(),
+            REG_OPTION_NON_VOLATILE,
+            KEY_WRITE,
+            None,
+            &mut hkey,
+            None,
+        )
+        .ok()?;
+    }
+    Ok(hkey)
+}
+
+fn open_run_key_for_read() -> Result<HKEY> {
+    let mut hkey = HKEY::default();
+    unsafe {
+        RegOpenKeyExW(
+            HKEY_CURRENT_USER,
+            PCWSTR(wide(RUN_KEY).as_ptr()),
+            0,
+            KEY_READ,
+            &mut hkey,
+        )
+        .ok()?;
+    }
+    Ok(hkey)
+}
+
+/// Register `command_line` to run at logon under `name`.
+///
+/// Re-registering the same `name` overwrites the previous command line.
+pub fn register_run_key(name: &str, command_line: &str) -> Result<()> {
+    let hkey = open_run_key_for_write()?;
+    let value = wide(command_line);
+    let value_bytes =
+        unsafe { std::slice::from_raw_parts(value.as_ptr() as *const u8, value.len() * 2) };
+    let result =
+        unsafe { RegSetValueExW(hkey, PCWSTR(wide(name).as_ptr()), 0, REG_SZ, Some(value_bytes)) };
+    unsafe {
+        let _ = RegCloseKey(hkey);
+    }
+    result.ok()
+}
+
+/// Remove a previously registered Run-key entry.
+pub fn unregister_run_key(name: &str) -> Result<()> {
+    let hkey = open_run_key_for_write()?;
+    let result = unsafe { RegDeleteValueW(hkey, PCWSTR(wide(name).as_ptr())) };
+    unsafe {
+        let _ = RegCloseKey(hkey);
+    }
+    result.ok()
+}
+
+/// Holds the named mutex created by [`is_already_running`] open for as long as this
+/// guard lives.
+///
+/// A named kernel mutex is destroyed the instant its last handle closes, so the
+/// "an instance is running" marker only exists for as long as this guard is kept
+/// alive — hold onto it for the life of the process (e.g. in a local in `main`)
+/// rather than dropping it right away.
+pub struct RunningInstanceGuard(HANDLE);
+
+impl Drop for RunningInstanceGuard {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
+
+/// Check whether another instance of this process is already running under
+/// `instance_name`, claiming the marker for this process if not.
+///
+/// A Run-key process isn't managed by the SCM, so there's no `query_status` to ask;
+/// this uses a named mutex as a singleton-instance marker so callers can emulate
+/// start (skip starting a second copy while a first one holds the marker). The
+/// running copy is not notified of anything. The returned guard must be kept alive for as long as this
+/// process wants to be counted as "running" — dropping it immediately releases the
+/// marker, which would make every subsequent check report `false`.
+pub fn is_already_running(instance_name: &str) -> Result<(bool, RunningInstanceGuard)> {
+    let mutex_name = wide(&format!(r"Local\{instance_name}"));
+    let handle = unsafe { CreateMutexW(None, false, PCWSTR(mutex_name.as_ptr()))? };
+    let already_running = unsafe { windows::Win32::Foundation::GetLastError() } == ERROR_ALREADY_EXISTS;
+    Ok((already_running, RunningInstanceGuard(handle)))
+}
+
+fn current_executable_path() -> Result<String> {
+    let mut buf: Vec<u16> = vec![0u16; 260];
+    loop {
+        let len = unsafe { GetModuleFileNameW(None, &mut buf) };
+        if len == 0 {
+            return Err(Error::from_win32());
+        }
+        if (len as usize) < buf.len() {
+            buf.truncate(len as usize);
+            return Ok(String::from_utf16_lossy(&buf));
+        }
+        buf.resize(buf.len() * 2, 0);
+    }
+}
+
+/// Check whether any value under the `Run` key references `exe_path`.
+///
+/// This is the strongest signal this crate has for "the current process was
+/// launched by a Run-key autostart entry": process ancestry alone can't tell a
+/// Run-key launch apart from a plain double-click, since Explorer spawns both as
+/// its own direct children.
+fn run_key_references(exe_path: &str) -> Result<bool> {
+    let hkey = open_run_key_for_read()?;
+    let needle = exe_path.to_ascii_lowercase();
+    let mut index = 0u32;
+    let found = loop {
+        let mut name_buf = [0u16; 256];
+        let mut name_len = name_buf.len() as u32;
+        let mut data_buf = [0u8; 2048];
+        let mut data_len = data_buf.len() as u32;
+        let status = unsafe {
+            RegEnumValueW(
+                hkey,
+                index,
+                PWSTR(name_buf.as_mut_ptr()),
+                &mut name_len,
+                None,
+                None,
+                Some(data_buf.as_mut_ptr()),
+                Some(&mut data_len),
+            )
+        };
+        if status == ERROR_NO_MORE_ITEMS {
+            break false;
+        }
+        if let Err(e) = status.ok() {
+            unsafe {
+                let _ = RegCloseKey(hkey);
+            }
+            return Err(e);
+        }
+
+        let value = String::from_utf16_lossy(unsafe {
+            std::slice::from_raw_parts(data_buf.as_ptr() as *const u16, data_len as usize / 2)
+        });
+        if value.to_ascii_lowercase().contains(&needle) {
+            break true;
+        }
+        index += 1;
+    };
+    unsafe {
+        let _ = RegCloseKey(hkey);
+    }
+    Ok(found)
+}
+
+/// Whether the currently running executable is itself registered under the `Run`
+/// key, for use by [`crate::detect_execution_environment`] to tell a Run-key
+/// autostart launch apart from a plain Explorer double-click.
+pub(crate) fn current_executable_is_autostart_registered() -> Result<bool> {
+    run_key_references(&current_executable_path()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_already_running_second_call_sees_the_first() {
+        let name = "windows-service-detector-rs-test-instance";
+        let (first_already_running, _first_guard) =
+            is_already_running(name).expect("error claiming marker");
+        assert!(!first_already_running);
+
+        let (second_already_running, _second_guard) =
+            is_already_running(name).expect("error checking marker");
+        assert!(second_already_running);
+    }
+}
@@ -0,0 +1,164 @@
+//! Richer execution-environment detection.
+//!
+//! [`is_running_as_windows_service`](crate::is_running_as_windows_service) only
+//! ever answers "service or not". Binaries that behave differently under a
+//! scheduled task or a logon autostart entry (as the duplicated
+//! `service_main` stubs across the ecosystem all do) need more than a bool,
+//! so this module walks the same parent-process chain and classifies it.
+
+use std::ffi::OsStr;
+use std::mem::size_of;
+use std::os::windows::ffi::OsStrExt;
+
+use windows::core::{Result, PCWSTR};
+use windows::Win32::System::Services::{
+    CloseServiceHandle, OpenSCManagerW, OpenServiceW, QueryServiceStatusEx, SC_MANAGER_CONNECT,
+    SC_STATUS_PROCESS_INFO, SERVICE_QUERY_STATUS, SERVICE_STATUS_PROCESS,
+};
+
+use crate::process::{self, ProcessEntry};
+
+/// How the current process came to be running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionEnvironment {
+    /// Started by the Service Control Manager: parent is `services.exe` in session 0.
+    WindowsService,
+    /// Started by the Task Scheduler: parent is `taskeng.exe`, or the specific
+    /// `svchost.exe` instance confirmed (via the SCM) to be hosting the `Schedule`
+    /// service.
+    ScheduledTask,
+    /// Registered to run automatically at user logon via an HKCU `Run` key: ancestry
+    /// traces back to `explorer.exe`/`userinit.exe` with no console host in between,
+    /// *and* the current executable is itself present in the `Run` key. Ancestry
+    /// alone can't tell this apart from a plain double-click, since Explorer spawns
+    /// both as its own direct children.
+    UserAutostart,
+    /// Running interactively from a console host, or launched directly by the user
+    /// (e.g. a double-click) with no matching `Run` key registration.
+    Interactive,
+}
+
+const SERVICE_HOST: &str = "services.exe";
+const TASK_ENGINE_HOST: &str = "taskeng.exe";
+const GENERIC_SERVICE_HOST: &str = "svchost.exe";
+const TASK_SCHEDULER_SERVICE_NAME: &str = "Schedule";
+const SHELL_HOSTS: &[&str] = &["explorer.exe", "userinit.exe"];
+const CONSOLE_HOSTS: &[&str] = &["cmd.exe", "powershell.exe", "pwsh.exe", "conhost.exe", "windowsterminal.exe"];
+
+/// Determine which [`ExecutionEnvironment`] the current process is running under.
+pub fn detect_execution_environment() -> Result<ExecutionEnvironment> {
+    let current = process::get_current_process()?;
+    let entries = process::snapshot_processes()?;
+
+    let parent = match process::find_verified_parent(&entries, &current) {
+        Some(parent) => parent,
+        None => return Ok(ExecutionEnvironment::Interactive),
+    };
+
+    classify(&entries, parent)
+}
+
+fn classify(entries: &[ProcessEntry], parent: &ProcessEntry) -> Result<ExecutionEnvironment> {
+    if parent.session_id == 0 && parent.image_name.eq_ignore_ascii_case(SERVICE_HOST) {
+        return Ok(ExecutionEnvironment::WindowsService);
+    }
+
+    if parent.image_name.eq_ignore_ascii_case(TASK_ENGINE_HOST) {
+        return Ok(ExecutionEnvironment::ScheduledTask);
+    }
+
+    if parent.image_name.eq_ignore_ascii_case(GENERIC_SERVICE_HOST)
+        && task_scheduler_process_id().is_some_and(|pid| pid as usize == parent.pid)
+    {
+        return Ok(ExecutionEnvironment::ScheduledTask);
+    }
+
+    if CONSOLE_HOSTS
+        .iter()
+        .any(|host| parent.image_name.eq_ignore_ascii_case(host))
+    {
+        return Ok(ExecutionEnvironment::Interactive);
+    }
+
+    if ancestor_is_shell(entries, parent) && crate::autostart::current_executable_is_autostart_registered()? {
+        return Ok(ExecutionEnvironment::UserAutostart);
+    }
+
+    Ok(ExecutionEnvironment::Interactive)
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain(std::iter::once(0)).collect()
+}
+
+/// Ask the SCM which process is currently hosting the `Schedule` service.
+///
+/// `svchost.exe` hosts dozens of unrelated services (print spooler, WMI, Windows
+/// Update, ...) that also spawn child processes, so a bare `svchost.exe` image-name
+/// match isn't enough to claim Task Scheduler hosting — this confirms the specific
+/// instance.
+fn task_scheduler_process_id() -> Option<u32> {
+    unsafe {
+        let scm =
+            OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_CONNECT).ok()?;
+        let service = OpenServiceW(
+            scm,
+            PCWSTR(wide(TASK_SCHEDULER_SERVICE_NAME).as_ptr()),
+            SERVICE_QUERY_STATUS,
+        );
+        let pid = service.ok().and_then(|service| {
+            let mut status = SERVICE_STATUS_PROCESS::default();
+            let mut bytes_needed = 0u32;
+            let result = QueryServiceStatusEx(
+                service,
+                SC_STATUS_PROCESS_INFO,
+                Some(std::slice::from_raw_parts_mut(
+                    &mut status as *mut _ as *mut u8,
+                    size_of::<SERVICE_STATUS_PROCESS>(),
+                )),
+                &mut bytes_needed,
+            );
+            let _ = CloseServiceHandle(service);
+            result.ok().map(|_| status.dwProcessId)
+        });
+        let _ = CloseServiceHandle(scm);
+        pid
+    }
+}
+
+/// Walk up from `start` looking for a process rooted at `explorer.exe`/`userinit.exe`
+/// with no console host in between, which is how items registered in the HKCU `Run`
+/// key get launched at logon.
+fn ancestor_is_shell(entries: &[ProcessEntry], start: &ProcessEntry) -> bool {
+    let mut current = start;
+    for _ in 0..16 {
+        if SHELL_HOSTS
+            .iter()
+            .any(|host| current.image_name.eq_ignore_ascii_case(host))
+        {
+            return true;
+        }
+        if CONSOLE_HOSTS
+            .iter()
+            .any(|host| current.image_name.eq_ignore_ascii_case(host))
+        {
+            return false;
+        }
+        current = match process::find_by_pid(entries, current.parent_pid) {
+            Some(next) if next.pid != current.pid => next,
+            _ => return false,
+        };
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_execution_environment_interactive() {
+        let env = detect_execution_environment().expect("error during detection");
+        assert_eq!(env, ExecutionEnvironment::Interactive);
+    }
+}
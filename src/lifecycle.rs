@@ -0,0 +1,178 @@
+//! Folds the "dispatch under the SCM, run inline otherwise" stub that every
+//! downstream user reimplements (see the external `service_main` stubs the
+//! example used to carry) into the library itself.
+//!
+//! Callers write their business logic once in a closure and get correct
+//! lifecycle handling under both hosts, without touching
+//! `define_windows_service!` or [`ServiceStatus`] directly.
+
+use std::ffi::OsString;
+use std::io;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use windows::Win32::Foundation::{BOOL, TRUE};
+use windows::Win32::System::Console::SetConsoleCtrlHandler;
+use windows_service::define_windows_service;
+use windows_service::service::{
+    ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus,
+    ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_dispatcher;
+use windows_service::{Error as ServiceError, Result as ServiceResult};
+
+use crate::{detect_execution_environment, ExecutionEnvironment};
+
+const SERVICE_TYPE: ServiceType = ServiceType::OWN_PROCESS;
+
+/// Tells the running body that it's time to stop, regardless of whether it's
+/// hosted as a service (a Stop/Shutdown control) or running in the foreground
+/// (Ctrl-C).
+pub struct ShutdownSignal {
+    rx: Receiver<()>,
+}
+
+impl ShutdownSignal {
+    /// Block until a stop has been requested.
+    pub fn wait(&self) {
+        let _ = self.rx.recv();
+    }
+
+    /// Block up to `timeout`, returning `true` if a stop was requested in that window.
+    ///
+    /// Also returns `true` if the sending side has been dropped, since no stop
+    /// request can arrive after that.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        match self.rx.recv_timeout(timeout) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce(ShutdownSignal) -> ServiceResult<()> + Send>;
+
+static PENDING_JOB: Mutex<Option<(String, Job)>> = Mutex::new(None);
+static CTRLC_TX: Mutex<Option<Sender<()>>> = Mutex::new(None);
+
+define_windows_service!(ffi_service_main, service_main);
+
+fn service_main(_args: Vec<OsString>) {
+    let _ = run_dispatched();
+}
+
+fn run_dispatched() -> ServiceResult<()> {
+    let (name, job) = PENDING_JOB
+        .lock()
+        .unwrap()
+        .take()
+        .expect("service_main invoked without a job queued by run_service_or_foreground");
+
+    let (shutdown_tx, shutdown_rx) = channel();
+    let event_handler = move |control_event| -> ServiceControlHandlerResult {
+        match control_event {
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                let _ = shutdown_tx.send(());
+                ServiceControlHandlerResult::NoError
+            }
+            _ => ServiceControlHandlerResult::NotImplemented,
+        }
+    };
+
+    let status_handle = service_control_handler::register(&name, event_handler)?;
+    status_handle.set_service_status(ServiceStatus {
+        service_type: SERVICE_TYPE,
+        current_state: ServiceState::Running,
+        controls_accepted: ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    })?;
+
+    let result = job(ShutdownSignal { rx: shutdown_rx });
+
+    status_handle.set_service_status(ServiceStatus {
+        service_type: SERVICE_TYPE,
+        current_state: ServiceState::Stopped,
+        controls_accepted: ServiceControlAccept::empty(),
+        exit_code: match &result {
+            Ok(()) => ServiceExitCode::Win32(0),
+            Err(_) => ServiceExitCode::Win32(1),
+        },
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    })?;
+
+    result
+}
+
+unsafe extern "system" fn console_ctrl_handler(_ctrl_type: u32) -> BOOL {
+    if let Some(tx) = CTRLC_TX.lock().unwrap().as_ref() {
+        let _ = tx.send(());
+    }
+    TRUE
+}
+
+fn run_foreground(body: impl FnOnce(ShutdownSignal) -> ServiceResult<()>) -> ServiceResult<()> {
+    let (shutdown_tx, shutdown_rx) = channel();
+    *CTRLC_TX.lock().unwrap() = Some(shutdown_tx);
+    unsafe { SetConsoleCtrlHandler(Some(console_ctrl_handler), true) }
+        .map_err(|e| ServiceError::Winapi(io::Error::from_raw_os_error(e.code().0)))?;
+
+    body(ShutdownSignal { rx: shutdown_rx })
+}
+
+/// Run `body` as a Windows Service named `name` when hosted by the SCM, or directly
+/// on the current thread otherwise, with Ctrl-C mapped to the same [`ShutdownSignal`].
+///
+/// This wires up `service_dispatcher::start`, registers a control handler that
+/// translates Stop/Shutdown into the signal `body` can wait on, and reports the
+/// `Running`/`Stopped` status transitions automatically. Under every other
+/// [`ExecutionEnvironment`], `body` just runs inline.
+pub fn run_service_or_foreground(
+    name: &str,
+    body: impl FnOnce(ShutdownSignal) -> ServiceResult<()> + Send + 'static,
+) -> ServiceResult<()> {
+    let is_service = matches!(
+        detect_execution_environment(),
+        Ok(ExecutionEnvironment::WindowsService)
+    );
+
+    if is_service {
+        *PENDING_JOB.lock().unwrap() = Some((name.to_owned(), Box::new(body)));
+        service_dispatcher::start(name, ffi_service_main)
+    } else {
+        run_foreground(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_shutdown_signal_wait_timeout() {
+        let (tx, rx) = channel();
+        let signal = ShutdownSignal { rx };
+
+        assert!(!signal.wait_timeout(Duration::from_millis(10)));
+
+        tx.send(()).unwrap();
+        assert!(signal.wait_timeout(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_shutdown_signal_wait_timeout_on_disconnect() {
+        let (tx, rx) = channel();
+        let signal = ShutdownSignal { rx };
+        thread::spawn(move || drop(tx)).join().unwrap();
+
+        assert!(signal.wait_timeout(Duration::from_millis(10)));
+    }
+}
@@ -0,0 +1,183 @@
+//! Installing, uninstalling, and controlling Windows Services via the SCM.
+//!
+//! Detection alone only answers "am I running as a service"; a binary that
+//! self-registers (`myapp.exe --install`) also needs to create, remove, and
+//! control that service. This wraps [`windows_service`]'s `ServiceManager`
+//! with the crate's own error type so callers can distinguish "already
+//! installed" / "not installed" from other SCM failures.
+
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+
+use windows_service::service::{
+    ServiceAccess, ServiceErrorControl, ServiceInfo, ServiceStartType, ServiceState, ServiceType,
+};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+
+const ERROR_SERVICE_EXISTS: i32 = 1073;
+const ERROR_SERVICE_DOES_NOT_EXIST: i32 = 1060;
+
+/// Errors returned by the service management API.
+#[derive(Debug)]
+pub enum ManagementError {
+    /// A service with this name is already registered with the SCM.
+    AlreadyExists,
+    /// No service with this name is registered with the SCM.
+    NotFound,
+    /// The underlying `windows_service`/Win32 call failed.
+    Os(windows_service::Error),
+}
+
+impl std::fmt::Display for ManagementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManagementError::AlreadyExists => write!(f, "service is already installed"),
+            ManagementError::NotFound => write!(f, "service is not installed"),
+            ManagementError::Os(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ManagementError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ManagementError::Os(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<windows_service::Error> for ManagementError {
+    fn from(e: windows_service::Error) -> Self {
+        match &e {
+            windows_service::Error::Winapi(io_err) => match io_err.raw_os_error() {
+                Some(ERROR_SERVICE_EXISTS) => ManagementError::AlreadyExists,
+                Some(ERROR_SERVICE_DOES_NOT_EXIST) => ManagementError::NotFound,
+                _ => ManagementError::Os(e),
+            },
+            _ => ManagementError::Os(e),
+        }
+    }
+}
+
+/// Result alias used throughout this module.
+pub type Result<T> = std::result::Result<T, ManagementError>;
+
+/// When a service should start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceStartMode {
+    /// Starts automatically at boot.
+    Auto,
+    /// Starts only when explicitly requested.
+    Demand,
+    /// Starts as part of boot-time driver loading; included for completeness,
+    /// not typically used by user-mode services.
+    Boot,
+}
+
+impl From<ServiceStartMode> for ServiceStartType {
+    fn from(mode: ServiceStartMode) -> Self {
+        match mode {
+            ServiceStartMode::Auto => ServiceStartType::AutoStart,
+            ServiceStartMode::Demand => ServiceStartType::OnDemand,
+            ServiceStartMode::Boot => ServiceStartType::BootStart,
+        }
+    }
+}
+
+/// Everything needed to register a new service with the SCM.
+#[derive(Debug, Clone)]
+pub struct ServiceSpec {
+    /// The service's internal name, as used by `sc.exe` and the SCM.
+    pub name: String,
+    /// The friendlier name shown in the Services MMC console.
+    pub display_name: String,
+    /// Shown alongside `display_name` in the Services console.
+    pub description: Option<String>,
+    /// Path to the executable the SCM should launch.
+    pub binary_path: PathBuf,
+    /// Arguments passed to `binary_path` on start.
+    pub args: Vec<OsString>,
+    /// Whether this runs in its own process or shares one with other services.
+    pub service_type: ServiceType,
+    /// When the SCM should start this service.
+    pub start_type: ServiceStartMode,
+}
+
+/// Install `spec` as a new service.
+///
+/// Returns [`ManagementError::AlreadyExists`] if a service with this name is
+/// already registered.
+pub fn install_service(spec: ServiceSpec) -> Result<()> {
+    let manager =
+        ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CREATE_SERVICE)?;
+
+    let service = manager.create_service(
+        &ServiceInfo {
+            name: OsString::from(&spec.name),
+            display_name: OsString::from(&spec.display_name),
+            service_type: spec.service_type,
+            start_type: spec.start_type.into(),
+            error_control: ServiceErrorControl::Normal,
+            executable_path: spec.binary_path,
+            launch_arguments: spec.args,
+            dependencies: vec![],
+            account_name: None,
+            account_password: None,
+        },
+        ServiceAccess::CHANGE_CONFIG,
+    )?;
+
+    if let Some(description) = spec.description {
+        service.set_description(description)?;
+    }
+
+    Ok(())
+}
+
+/// Remove a previously installed service.
+///
+/// Returns [`ManagementError::NotFound`] if no such service is registered.
+pub fn uninstall_service(name: &str) -> Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(name, ServiceAccess::DELETE)?;
+    service.delete()?;
+    Ok(())
+}
+
+/// Start a previously installed service.
+pub fn start_service(name: &str) -> Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(name, ServiceAccess::START)?;
+    service.start(&[] as &[&OsStr])?;
+    Ok(())
+}
+
+/// Ask a running service to stop.
+pub fn stop_service(name: &str) -> Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(name, ServiceAccess::STOP)?;
+    service.stop()?;
+    Ok(())
+}
+
+/// Query the current run state of a service.
+pub fn query_status(name: &str) -> Result<ServiceState> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(name, ServiceAccess::QUERY_STATUS)?;
+    Ok(service.query_status()?.current_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_status_nonexistent_service() {
+        let name = "windows-service-detector-rs-test-nonexistent";
+        match query_status(name) {
+            Err(ManagementError::NotFound) => (),
+            other => panic!("expected ManagementError::NotFound, got {other:?}"),
+        }
+    }
+}
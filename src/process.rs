@@ -0,0 +1,203 @@
+//! Low-level helpers for walking the Windows process tree.
+//!
+//! These wrap the same `NtQuerySystemInformation` / `NtQueryInformationProcess`
+//! plumbing the original single-function detector used, factored out so the
+//! higher-level environment checks can share one process snapshot instead of
+//! re-querying the kernel for each candidate host.
+
+use std::mem::size_of;
+use std::os::raw::c_void;
+use windows::core::Result;
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::Foundation::FILETIME;
+use windows::Win32::Foundation::STATUS_INFO_LENGTH_MISMATCH;
+use windows::Win32::System::Threading::GetCurrentProcess;
+use windows::Win32::System::Threading::GetProcessTimes;
+use windows::Win32::System::Threading::NtQueryInformationProcess;
+use windows::Win32::System::Threading::ProcessBasicInformation;
+use windows::Win32::System::Threading::PROCESS_BASIC_INFORMATION;
+use windows::Win32::System::WindowsProgramming::NtQuerySystemInformation;
+use windows::Win32::System::WindowsProgramming::SystemProcessInformation;
+use windows::Win32::System::WindowsProgramming::SYSTEM_PROCESS_INFORMATION;
+
+/// Initial guess at the buffer size `NtQuerySystemInformation` will need, to avoid
+/// starting from a zero-length allocation whose pointer isn't a valid write target.
+const INITIAL_BUFFER_SIZE: usize = 128 * 1024;
+/// Extra slack added on top of the size the kernel reports, so a process that starts
+/// between our size query and our actual query doesn't immediately force a retry.
+const BUFFER_SLACK: usize = 4 * 1024;
+
+/// The subset of `SYSTEM_PROCESS_INFORMATION` the environment checks need.
+#[derive(Debug, Clone)]
+pub(crate) struct ProcessEntry {
+    pub(crate) pid: usize,
+    pub(crate) parent_pid: usize,
+    pub(crate) session_id: u32,
+    pub(crate) image_name: String,
+    pub(crate) create_time: i64,
+}
+
+/// The identity of the current process needed to walk to its real parent.
+pub(crate) struct CurrentProcess {
+    pub(crate) parent_pid: usize,
+    pub(crate) create_time: i64,
+}
+
+fn filetime_to_i64(ft: FILETIME) -> i64 {
+    ((ft.dwHighDateTime as i64) << 32) | ft.dwLowDateTime as i64
+}
+
+/// Return the parent process id and creation time of the current process.
+pub(crate) fn get_current_process() -> Result<CurrentProcess> {
+    unsafe {
+        let phdl = GetCurrentProcess();
+        let mut pinfo = PROCESS_BASIC_INFORMATION::default();
+        let mut pinfosz: u32 = 0;
+        let res = NtQueryInformationProcess(
+            phdl,
+            ProcessBasicInformation,
+            &mut pinfo as *mut _ as *mut c_void,
+            size_of::<PROCESS_BASIC_INFORMATION>() as u32,
+            &mut pinfosz,
+        );
+
+        let result = res.map(|_| ()).and_then(|_| {
+            let mut creation = FILETIME::default();
+            let mut exit = FILETIME::default();
+            let mut kernel = FILETIME::default();
+            let mut user = FILETIME::default();
+            GetProcessTimes(phdl, &mut creation, &mut exit, &mut kernel, &mut user)?;
+            Ok(CurrentProcess {
+                parent_pid: pinfo.InheritedFromUniqueProcessId,
+                create_time: filetime_to_i64(creation),
+            })
+        });
+
+        CloseHandle(phdl);
+        result.map_err(Into::into)
+    }
+}
+
+/// Snapshot every process currently visible to `NtQuerySystemInformation`.
+pub(crate) fn snapshot_processes() -> Result<Vec<ProcessEntry>> {
+    let mut sys_procs_buf: Vec<u8> = vec![0u8; INITIAL_BUFFER_SIZE];
+    let mut return_len: u32 = 0;
+
+    loop {
+        unsafe {
+            match NtQuerySystemInformation(
+                SystemProcessInformation,
+                sys_procs_buf.as_mut_ptr() as *mut c_void,
+                sys_procs_buf.len() as u32,
+                &mut return_len,
+            ) {
+                Ok(()) => break,
+                Err(e) if e.code() == STATUS_INFO_LENGTH_MISMATCH.into() => {
+                    sys_procs_buf.resize(return_len as usize + BUFFER_SLACK, 0);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    let mut entries = Vec::new();
+    unsafe {
+        let mut ptr = sys_procs_buf.as_mut_ptr();
+        let end = sys_procs_buf.as_mut_ptr().add(sys_procs_buf.len());
+        while ptr < end {
+            let proc = (ptr as *mut SYSTEM_PROCESS_INFORMATION).as_ref().unwrap();
+            let image_name = if proc.ImageName.Buffer.is_null() {
+                "<null>".to_owned()
+            } else {
+                proc.ImageName
+                    .Buffer
+                    .to_string()
+                    .unwrap_or_else(|_| "<invalid>".to_owned())
+            };
+            entries.push(ProcessEntry {
+                pid: proc.UniqueProcessId.0 as usize,
+                parent_pid: proc.InheritedFromUniqueProcessId.0 as usize,
+                session_id: proc.SessionId,
+                image_name,
+                create_time: proc.CreateTime,
+            });
+
+            let next_offset = proc.NextEntryOffset as usize;
+            if next_offset == 0 {
+                break;
+            }
+            ptr = ptr.add(next_offset);
+        }
+    }
+    Ok(entries)
+}
+
+/// Find a process entry by PID in a snapshot.
+pub(crate) fn find_by_pid(entries: &[ProcessEntry], pid: usize) -> Option<&ProcessEntry> {
+    entries.iter().find(|entry| entry.pid == pid)
+}
+
+/// Find `current`'s real parent in `entries`.
+///
+/// Windows recycles PIDs, so a long-lived child can inherit a PID that now belongs
+/// to an unrelated, newer process. A real parent must have started *before* its
+/// child, so a PID match whose `CreateTime` is not earlier than `current`'s is
+/// rejected as a stale reference rather than reported as the parent.
+pub(crate) fn find_verified_parent<'a>(
+    entries: &'a [ProcessEntry],
+    current: &CurrentProcess,
+) -> Option<&'a ProcessEntry> {
+    find_by_pid(entries, current.parent_pid).filter(|parent| parent.create_time < current.create_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: usize, image_name: &str, create_time: i64) -> ProcessEntry {
+        ProcessEntry {
+            pid,
+            parent_pid: 0,
+            session_id: 1,
+            image_name: image_name.to_owned(),
+            create_time,
+        }
+    }
+
+    #[test]
+    fn test_find_verified_parent_rejects_reused_pid() {
+        // A long-lived child (create_time 100) whose recorded parent_pid (4) now
+        // belongs to an unrelated process that started later (create_time 200) -
+        // the real parent exited and the kernel recycled its PID.
+        let entries = vec![entry(4, "newcomer.exe", 200)];
+        let current = CurrentProcess {
+            parent_pid: 4,
+            create_time: 100,
+        };
+
+        assert!(find_verified_parent(&entries, &current).is_none());
+    }
+
+    #[test]
+    fn test_find_verified_parent_accepts_genuine_parent() {
+        let entries = vec![entry(4, "services.exe", 50)];
+        let current = CurrentProcess {
+            parent_pid: 4,
+            create_time: 100,
+        };
+
+        let parent = find_verified_parent(&entries, &current).expect("expected a verified parent");
+        assert_eq!(parent.image_name, "services.exe");
+    }
+
+    #[test]
+    fn test_find_verified_parent_no_pid_match() {
+        let entries = vec![entry(7, "unrelated.exe", 50)];
+        let current = CurrentProcess {
+            parent_pid: 4,
+            create_time: 100,
+        };
+
+        assert!(find_verified_parent(&entries, &current).is_none());
+    }
+}